@@ -0,0 +1,5 @@
+use std::{env::args, process::ExitCode};
+
+fn main() -> anyhow::Result<ExitCode> {
+    whats_changed::cli::run(true, args().skip(1))
+}
@@ -0,0 +1,716 @@
+//! Command-line interface shared by the `whats-changed` and `cargo-whats-changed` binaries
+//!
+//! Unlike the rest of the library, this module prints its results. It is not part of the
+//! documented API.
+
+use crate::{
+    Bump, ChangeKind, CompareError, CompareOptions, DepKind, DepSource, DependencyChange,
+    GitReference, LockfileChange, LockfileReport, ManifestReport, compare_lockfiles,
+    compare_revisions, most_recent_tag, workspace_manifests,
+};
+use anyhow::{Result, bail};
+use elaborate::std::env::current_dir_wc;
+use semver::{Op, VersionReq};
+use serde_json::json;
+use std::{collections::BTreeSet, path::PathBuf, process::ExitCode};
+
+struct Options {
+    prev_rev: Option<String>,
+    curr_rev: Option<String>,
+    /// As with other Cargo subcommands; accepted only when run as `cargo whats-changed`
+    manifest_path: Option<PathBuf>,
+    packages: Vec<String>,
+    workspace: bool,
+    compare: CompareOptions,
+    format: Format,
+    flat: bool,
+    lockfile: bool,
+    breaking_only: bool,
+    verbatim: bool,
+    fail_on: BTreeSet<FailOn>,
+}
+
+/// A condition under which `--fail-on` makes whats-changed exit with a nonzero status
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+enum FailOn {
+    /// Any change was reported
+    Any,
+    /// A semver-breaking upgrade or downgrade was reported
+    Breaking,
+    /// A removal was reported
+    Removed,
+    /// A dependency could not be compared
+    Error,
+}
+
+impl FailOn {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Any => "any",
+            Self::Breaking => "breaking",
+            Self::Removed => "removed",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Exit status when a reported change meets a `--fail-on` condition
+const EXIT_CHANGES: u8 = 2;
+
+/// Exit status when a dependency could not be compared and `--fail-on` includes `error`
+const EXIT_ERRORS: u8 = 3;
+
+#[derive(Clone, Copy)]
+enum Format {
+    Text,
+    Json,
+    Markdown,
+}
+
+/// Runs whats-changed with `args`, which exclude the program name, and returns its exit status
+///
+/// If `subcommand` is true, whats-changed is being run as `cargo whats-changed`. The manifests to
+/// compare are then the workspace members found by `cargo metadata`, rather than every Cargo.toml
+/// file in the repository.
+pub fn run(subcommand: bool, args: impl IntoIterator<Item = String>) -> Result<ExitCode> {
+    let mut options = parse_args(subcommand, args)?;
+    let repo = current_dir_wc()?;
+    if subcommand {
+        let manifests = workspace_manifests(
+            &repo,
+            options.manifest_path.as_deref(),
+            &options.packages,
+            options.workspace,
+        )?;
+        options.compare.manifests = Some(manifests);
+    }
+    let prev_rev = if let Some(prev_rev) = &options.prev_rev {
+        prev_rev.clone()
+    } else {
+        let tag = most_recent_tag(&repo)?;
+        eprintln!("No revision specified; using most recent tag: {tag}");
+        tag
+    };
+    if options.lockfile {
+        let mut reports = compare_lockfiles(&repo, &prev_rev, options.curr_rev.as_deref())?;
+        if options.breaking_only {
+            for report in &mut reports {
+                report.changes.retain(|change| is_breaking(change.bump));
+            }
+        }
+        match options.format {
+            Format::Text => print_lockfile_text(&reports),
+            Format::Json => print_lockfile_json(&reports)?,
+            Format::Markdown => print_lockfile_markdown(&reports, options.flat),
+        }
+        let changes = reports
+            .iter()
+            .flat_map(|report| &report.changes)
+            .map(|change| (change.kind, change.bump));
+        return Ok(exit_status(&options.fail_on, changes, false));
+    }
+    let mut reports = compare_revisions(
+        &repo,
+        &options.compare,
+        &prev_rev,
+        options.curr_rev.as_deref(),
+    )?;
+    // smoelius: With `--added`, a new manifest's dependencies are reported as added. So the
+    // manifest needs no separate mention unless it has no dependencies.
+    for report in reports
+        .iter()
+        .filter(|report| report.added && (!options.compare.added || report.is_empty()))
+    {
+        eprintln!(
+            "`{}` does not exist in previous revision",
+            report.path.display()
+        );
+    }
+    if options.breaking_only {
+        for report in &mut reports {
+            report.changes.retain(|change| is_breaking(change.bump));
+        }
+    }
+    match options.format {
+        Format::Text => print_text(&reports, options.verbatim),
+        Format::Json => print_json(&reports)?,
+        Format::Markdown => print_markdown(&reports, options.flat, options.verbatim),
+    }
+    let changes = reports
+        .iter()
+        .flat_map(|report| &report.changes)
+        .map(|change| (change.kind, change.bump));
+    let errors = reports.iter().any(|report| !report.errors.is_empty());
+    Ok(exit_status(&options.fail_on, changes, errors))
+}
+
+/// Returns a nonzero exit status if the reported changes or errors meet a condition in `fail_on`
+///
+/// Errors take precedence over changes, i.e., if both meet a condition, the exit status is
+/// `EXIT_ERRORS`.
+fn exit_status(
+    fail_on: &BTreeSet<FailOn>,
+    mut changes: impl Iterator<Item = (ChangeKind, Option<Bump>)>,
+    errors: bool,
+) -> ExitCode {
+    if errors && fail_on.contains(&FailOn::Error) {
+        return ExitCode::from(EXIT_ERRORS);
+    }
+    if changes.any(|(kind, bump)| {
+        fail_on.contains(&FailOn::Any)
+            || (fail_on.contains(&FailOn::Breaking) && is_breaking(bump))
+            || (fail_on.contains(&FailOn::Removed) && kind == ChangeKind::Removed)
+    }) {
+        return ExitCode::from(EXIT_CHANGES);
+    }
+    ExitCode::SUCCESS
+}
+
+fn parse_args(subcommand: bool, args: impl IntoIterator<Item = String>) -> Result<Options> {
+    let mut options = Options {
+        prev_rev: None,
+        curr_rev: None,
+        manifest_path: None,
+        packages: Vec::new(),
+        workspace: false,
+        compare: CompareOptions::default(),
+        format: Format::Text,
+        flat: false,
+        lockfile: false,
+        breaking_only: false,
+        verbatim: false,
+        fail_on: BTreeSet::new(),
+    };
+    let mut args = args.into_iter().peekable();
+    // smoelius: Cargo passes the subcommand's name as the first argument.
+    if subcommand && args.peek().map(String::as_str) == Some("whats-changed") {
+        args.next();
+    }
+    while let Some(arg) = args.next() {
+        if subcommand && let Some(value) = option_value(&arg, "--manifest-path", &mut args)? {
+            options.manifest_path = Some(PathBuf::from(value));
+        } else if subcommand && let Some(value) = option_value(&arg, "--package", &mut args)? {
+            options.packages.push(value);
+        } else if subcommand && let Some(value) = option_value(&arg, "-p", &mut args)? {
+            options.packages.push(value);
+        } else if subcommand && arg == "--workspace" {
+            options.workspace = true;
+        } else if let Some(value) = option_value(&arg, "--kinds", &mut args)? {
+            options.compare.kinds = parse_kinds(&value)?;
+        } else if let Some(value) = option_value(&arg, "--fail-on", &mut args)? {
+            options.fail_on.extend(parse_fail_on(&value)?);
+        } else if let Some(value) = option_value(&arg, "--format", &mut args)? {
+            options.format = match value.as_str() {
+                "text" => Format::Text,
+                "json" => Format::Json,
+                "markdown" => Format::Markdown,
+                _ => bail!("unknown format: {value}"),
+            };
+        } else if arg == "--added" {
+            options.compare.added = true;
+        } else if arg == "--flat" {
+            options.flat = true;
+        } else if arg == "--lockfile" {
+            options.lockfile = true;
+        } else if arg == "--breaking-only" {
+            options.breaking_only = true;
+        } else if arg == "--verbatim" {
+            options.verbatim = true;
+        } else if arg.starts_with('-') {
+            bail!("unrecognized option: {arg}");
+        } else if options.prev_rev.is_none() {
+            options.prev_rev = Some(arg);
+        } else if subcommand {
+            // smoelius: Workspace members are found with `cargo metadata`, which reads the working
+            // tree. So they cannot be found for another revision.
+            bail!(
+                "`cargo whats-changed` compares against the working tree and accepts only one \
+                 revision; to compare two revisions, use `whats-changed PREVIOUS CURRENT`"
+            );
+        } else if options.curr_rev.is_none() {
+            options.curr_rev = Some(arg);
+        } else {
+            bail!("expect at most two arguments: previous and current revisions");
+        }
+    }
+    Ok(options)
+}
+
+/// Returns the value of option `name` if `arg` is `name`, or is of the form `name=value`
+///
+/// In the former case, the value is taken from `args`.
+fn option_value(
+    arg: &str,
+    name: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<Option<String>> {
+    if arg == name {
+        let Some(value) = args.next() else {
+            bail!("`{name}` requires a value");
+        };
+        Ok(Some(value))
+    } else if let Some(value) = arg
+        .strip_prefix(name)
+        .and_then(|suffix| suffix.strip_prefix('='))
+    {
+        Ok(Some(value.to_owned()))
+    } else {
+        Ok(None)
+    }
+}
+
+fn parse_fail_on(value: &str) -> Result<BTreeSet<FailOn>> {
+    value
+        .split(',')
+        .map(|condition| {
+            let Some(condition) = [
+                FailOn::Any,
+                FailOn::Breaking,
+                FailOn::Removed,
+                FailOn::Error,
+            ]
+            .into_iter()
+            .find(|candidate| candidate.name() == condition) else {
+                bail!("unknown failure condition: {condition}");
+            };
+            Ok(condition)
+        })
+        .collect()
+}
+
+fn parse_kinds(value: &str) -> Result<BTreeSet<DepKind>> {
+    value
+        .split(',')
+        .map(|kind| {
+            let Some(kind) = [DepKind::Normal, DepKind::Dev, DepKind::Build]
+                .into_iter()
+                .find(|candidate| candidate.name() == kind)
+            else {
+                bail!("unknown dependency kind: {kind}");
+            };
+            Ok(kind)
+        })
+        .collect()
+}
+
+fn is_breaking(bump: Option<Bump>) -> bool {
+    bump.is_some_and(|bump| bump.breaking)
+}
+
+fn print_text(reports: &[ManifestReport], verbatim: bool) {
+    for report in reports {
+        if report.is_empty() {
+            continue;
+        }
+        println!("{}", report.describe(""));
+        for change in &report.changes {
+            println!(
+                "    {} [{}]",
+                describe_change(change, verbatim),
+                change.table.label()
+            );
+        }
+        for CompareError { table, name, error } in &report.errors {
+            eprintln!("failed to compare `{name}` [{}]: {error}", table.label());
+        }
+    }
+}
+
+/// Prints the changes as nested Markdown bullets, grouped by manifest, suitable for pasting into a
+/// changelog
+///
+/// If `flat` is true and only one manifest has changes, the manifest is omitted and the changes are
+/// printed as a flat list. The manifest is still printed if it was added, moved, or deleted, as its
+/// description then says something the changes do not.
+fn print_markdown(reports: &[ManifestReport], flat: bool, verbatim: bool) {
+    let reports = reports
+        .iter()
+        .filter(|report| !report.is_empty())
+        .collect::<Vec<_>>();
+    let flat = flat && reports.len() == 1;
+    for report in reports {
+        let annotated = report.added || report.deleted || report.prev_path != report.path;
+        let indent = if flat && !annotated { "" } else { "  " };
+        if !indent.is_empty() && (report.deleted || !report.changes.is_empty()) {
+            println!("- {}", report.describe("`"));
+        }
+        for change in &report.changes {
+            let table = &change.table;
+            // smoelius: Changes to the tables that declare a package's or workspace's ordinary
+            // dependencies are listed without a label, as in this crate's own changelog.
+            if table.target.is_none() && matches!(table.kind, DepKind::Normal | DepKind::Workspace)
+            {
+                println!("{indent}- {}", describe_change(change, verbatim));
+            } else {
+                println!(
+                    "{indent}- {} (`{}`)",
+                    describe_change(change, verbatim),
+                    table.label()
+                );
+            }
+        }
+        for CompareError { table, name, error } in &report.errors {
+            eprintln!(
+                "{}: failed to compare `{name}` [{}]: {error}",
+                report.path.display(),
+                table.label()
+            );
+        }
+    }
+}
+
+/// Describes `change`
+///
+/// If `verbatim` is true, version requirements are shown as written in the manifests, operators
+/// included. Otherwise, they are shown as by `format_req_version`.
+fn describe_change(change: &DependencyChange, verbatim: bool) -> String {
+    let DependencyChange {
+        name,
+        prev_name,
+        kind,
+        req_prev,
+        req_curr,
+        req_prev_verbatim,
+        req_curr_verbatim,
+        source_prev,
+        source_curr,
+        features,
+        bump,
+        minimum_version,
+        ..
+    } = change;
+    let format_req = |req: &Option<VersionReq>, req_verbatim: &Option<String>| {
+        req.as_ref().map(|req| match req_verbatim {
+            Some(req_verbatim) if verbatim => req_verbatim.clone(),
+            _ => format_req_version(req),
+        })
+    };
+    // smoelius: Path dependencies refer to crates in the same repository, which the user may want
+    // to distinguish from third-party ones. Similarly, dependencies from alternate registries are
+    // labeled with their registries, except where the message already describes the registries.
+    let dep = if change.is_internal() {
+        format!("`{name}` (internal)")
+    } else if let Some(registry) = change.registry()
+        && !matches!(
+            kind,
+            ChangeKind::SourceChanged | ChangeKind::RegistryChanged
+        )
+    {
+        format!("`{name}` (registry `{registry}`)")
+    } else {
+        format!("`{name}`")
+    };
+    let version_prev = format_req(req_prev, req_prev_verbatim);
+    let version_curr = format_req(req_curr, req_curr_verbatim);
+    match (kind, &version_prev, &version_curr) {
+        (ChangeKind::SourceChanged, _, _) => format!(
+            "{dep} changed from {} to {}",
+            describe_source(source_prev.as_ref(), version_prev.as_deref()),
+            describe_source(source_curr.as_ref(), version_curr.as_deref())
+        ),
+        (ChangeKind::RegistryChanged, _, _) => format!(
+            "{dep} registry changed from {} to {}",
+            describe_source(source_prev.as_ref(), version_prev.as_deref()),
+            describe_source(source_curr.as_ref(), version_curr.as_deref())
+        ),
+        (ChangeKind::GitUrlChanged, _, _) => format!(
+            "{dep} git URL changed from {} to {}",
+            describe_git_url(source_prev.as_ref()),
+            describe_git_url(source_curr.as_ref())
+        ),
+        (ChangeKind::GitReferenceChanged, _, _) => {
+            describe_git_reference_change(&change.name, source_prev.as_ref(), source_curr.as_ref())
+        }
+        (ChangeKind::FeaturesAdded | ChangeKind::FeaturesRemoved, _, _) => {
+            let verb = if *kind == ChangeKind::FeaturesAdded {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            let noun = if features.len() == 1 {
+                "feature"
+            } else {
+                "features"
+            };
+            let features = features
+                .iter()
+                .map(|feature| format!("`{feature}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{dep} {noun} {features} {verb}")
+        }
+        (ChangeKind::DefaultFeaturesEnabled, _, _) => format!("{dep} default features enabled"),
+        (ChangeKind::DefaultFeaturesDisabled, _, _) => {
+            format!("{dep} default features disabled")
+        }
+        (ChangeKind::MadeOptional, _, _) => format!("{dep} made optional"),
+        (ChangeKind::NoLongerOptional, _, _) => format!("{dep} no longer optional"),
+        (ChangeKind::Renamed, _, _) => format!("`{prev_name}` renamed to {dep}"),
+        (ChangeKind::Upgraded | ChangeKind::Downgraded, Some(version_prev), Some(version_curr)) => {
+            format!(
+                "{dep} {} from {version_prev} to {version_curr}{}",
+                kind.as_str(),
+                describe_bump(*bump)
+            )
+        }
+        // smoelius: An added dependency is described by the least version its requirement admits,
+        // e.g., `>=1.2, <2` by 1.2.0, unless requirements are shown verbatim.
+        (ChangeKind::Added, _, Some(version_curr)) => match minimum_version {
+            Some(minimum_version) if !verbatim => {
+                format!("{dep} added at version {minimum_version}")
+            }
+            _ => format!("{dep} added at version {version_curr}"),
+        },
+        (ChangeKind::Inherited, _, _) => format!("{dep} now inherited from the workspace"),
+        (ChangeKind::NoLongerInherited, _, _) => {
+            format!("{dep} no longer inherited from the workspace")
+        }
+        (_, _, _) => format!("{dep} {}", kind.as_str()),
+    }
+}
+
+fn print_lockfile_text(reports: &[LockfileReport]) {
+    for report in reports {
+        if report.changes.is_empty() {
+            continue;
+        }
+        println!("{}", report.path.display());
+        for change in &report.changes {
+            println!("    {}", describe_lockfile_change(change));
+        }
+    }
+}
+
+/// Prints the changes as nested Markdown bullets, grouped by lockfile, as `print_markdown` does
+fn print_lockfile_markdown(reports: &[LockfileReport], flat: bool) {
+    let reports = reports
+        .iter()
+        .filter(|report| !report.changes.is_empty())
+        .collect::<Vec<_>>();
+    let indent = if flat && reports.len() == 1 { "" } else { "  " };
+    for report in reports {
+        if !indent.is_empty() {
+            println!("- `{}`", report.path.display());
+        }
+        for change in &report.changes {
+            println!("{indent}- {}", describe_lockfile_change(change));
+        }
+    }
+}
+
+fn print_lockfile_json(reports: &[LockfileReport]) -> Result<()> {
+    let mut changes = Vec::new();
+    for report in reports {
+        let lockfile = report.path.to_string_lossy();
+        for change in &report.changes {
+            changes.push(json!({
+                "lockfile": lockfile,
+                "name": change.name,
+                "source": change.source,
+                "change": change.kind.as_str(),
+                "prev": change.version_prev.as_ref().map(ToString::to_string),
+                "curr": change.version_curr.as_ref().map(ToString::to_string),
+                "bump": change.bump.map(|bump| bump.level.as_str()),
+                "breaking": change.bump.map(|bump| bump.breaking),
+            }));
+        }
+    }
+    let document = json!({ "changes": changes });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+fn describe_lockfile_change(change: &LockfileChange) -> String {
+    let LockfileChange {
+        name,
+        source,
+        kind,
+        version_prev,
+        version_curr,
+        bump,
+    } = change;
+    // smoelius: As in manifest mode, packages not from crates.io are labeled with their sources.
+    let package = if let Some(source) = source {
+        format!("`{name}` (source `{source}`)")
+    } else {
+        format!("`{name}`")
+    };
+    match (kind, version_prev, version_curr) {
+        (ChangeKind::Upgraded | ChangeKind::Downgraded, Some(version_prev), Some(version_curr)) => {
+            format!(
+                "{package} {} from {version_prev} to {version_curr}{}",
+                kind.as_str(),
+                describe_bump(*bump)
+            )
+        }
+        (ChangeKind::Added, _, Some(version_curr)) => {
+            format!("{package} added at version {version_curr}")
+        }
+        (ChangeKind::Removed, Some(version_prev), _) => {
+            format!("{package} {version_prev} removed")
+        }
+        (_, _, _) => format!("{package} {}", kind.as_str()),
+    }
+}
+
+/// Returns a suffix describing `bump`, e.g., " (minor, breaking)", or an empty string if `bump` is
+/// `None`
+fn describe_bump(bump: Option<Bump>) -> String {
+    match bump {
+        Some(Bump {
+            level,
+            breaking: true,
+        }) => format!(" ({}, breaking)", level.as_str()),
+        Some(Bump {
+            level,
+            breaking: false,
+        }) => format!(" ({})", level.as_str()),
+        None => String::new(),
+    }
+}
+
+/// Describes `source`, including `version` if the source is a registry
+fn describe_source(source: Option<&DepSource>, version: Option<&str>) -> String {
+    match (source, version) {
+        (Some(source @ DepSource::Registry { .. }), Some(version)) => {
+            format!("{source} {version}")
+        }
+        (Some(source), _) => source.to_string(),
+        (None, _) => "an unknown source".to_owned(),
+    }
+}
+
+fn describe_git_url(source: Option<&DepSource>) -> &str {
+    match source {
+        Some(DepSource::Git { url, .. }) => url,
+        _ => "an unknown URL",
+    }
+}
+
+/// Describes a change to a git dependency's reference
+///
+/// A change in the reference's value alone is described in terms of the reference's key, e.g.,
+/// "`foo` branch changed from main to release". Otherwise, the whole references are described,
+/// e.g., "`foo` git reference changed from branch main to rev abc123".
+fn describe_git_reference_change(
+    name: &str,
+    source_prev: Option<&DepSource>,
+    source_curr: Option<&DepSource>,
+) -> String {
+    let git_reference = |source: Option<&DepSource>| match source {
+        Some(DepSource::Git { reference, .. }) => reference.clone(),
+        _ => None,
+    };
+    match (git_reference(source_prev), git_reference(source_curr)) {
+        (Some(reference_prev), Some(reference_curr))
+            if reference_prev.key() == reference_curr.key() =>
+        {
+            format!(
+                "`{name}` {} changed from {} to {}",
+                reference_curr.noun(),
+                reference_prev.value(),
+                reference_curr.value()
+            )
+        }
+        (reference_prev, reference_curr) => {
+            let describe = |reference: Option<GitReference>| {
+                reference.map_or_else(
+                    || "the default branch".to_owned(),
+                    |reference| reference.to_string(),
+                )
+            };
+            format!(
+                "`{name}` git reference changed from {} to {}",
+                describe(reference_prev),
+                describe(reference_curr)
+            )
+        }
+    }
+}
+
+fn print_json(reports: &[ManifestReport]) -> Result<()> {
+    let mut deleted_manifests = Vec::new();
+    let mut changes = Vec::new();
+    let mut errors = Vec::new();
+    for report in reports {
+        let manifest = report.path.to_string_lossy();
+        let prev_manifest = report.prev_path.to_string_lossy();
+        if report.deleted {
+            deleted_manifests.push(manifest.clone());
+        }
+        for change in &report.changes {
+            changes.push(json!({
+                "manifest": manifest,
+                "prev_manifest": prev_manifest,
+                "table": change.table.label(),
+                "kind": change.table.kind.name(),
+                "target": change.table.target,
+                "name": change.name,
+                "prev_name": change.prev_name,
+                "change": change.kind.as_str(),
+                "prev": change.req_prev.as_ref().map(ToString::to_string),
+                "curr": change.req_curr.as_ref().map(ToString::to_string),
+                "prev_source": change.source_prev.as_ref().map(source_json),
+                "curr_source": change.source_curr.as_ref().map(source_json),
+                "features": change.features,
+                "internal": change.is_internal(),
+                "registry": change.registry(),
+                "bump": change.bump.map(|bump| bump.level.as_str()),
+                "breaking": change.bump.map(|bump| bump.breaking),
+            }));
+        }
+        for CompareError { table, name, error } in &report.errors {
+            errors.push(json!({
+                "manifest": manifest,
+                "table": table.label(),
+                "kind": table.kind.name(),
+                "target": table.target,
+                "name": name,
+                "error": error.to_string(),
+            }));
+        }
+    }
+    let document = json!({
+        "deleted_manifests": deleted_manifests,
+        "changes": changes,
+        "errors": errors,
+    });
+    println!("{}", serde_json::to_string_pretty(&document)?);
+    Ok(())
+}
+
+fn source_json(source: &DepSource) -> serde_json::Value {
+    match source {
+        DepSource::Registry { name } => json!({ "kind": source.kind(), "registry": name }),
+        DepSource::Git { url, reference } => {
+            let mut value = json!({ "kind": source.kind(), "url": url });
+            if let Some(reference) = reference {
+                value[reference.key()] = json!(reference.value());
+            }
+            value
+        }
+        DepSource::Path { path } => json!({ "kind": source.kind(), "path": path }),
+    }
+}
+
+/// Returns `req` with caret operators removed, e.g., `^1.2` becomes `1.2`
+///
+/// Other operators are kept, e.g., `=1.2.3` and `>=1.2, <2` are returned unchanged, so that a
+/// change between `=1.2.3` and `1.2.3` remains visible.
+fn format_req_version(req: &VersionReq) -> String {
+    if req.comparators.is_empty() {
+        return req.to_string();
+    }
+    req.comparators
+        .iter()
+        .map(|comparator| {
+            let comparator_with_op = comparator.to_string();
+            if comparator.op == Op::Caret {
+                comparator_with_op.trim_start_matches('^').to_owned()
+            } else {
+                comparator_with_op
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,1783 @@
+//! Compare the Rust dependencies of two revisions of a Git repository
+//!
+//! [`compare_revisions`] compares the dependencies declared in Cargo.toml files, and
+//! [`compare_lockfiles`] compares the versions locked in Cargo.lock files. Both return typed
+//! reports, leaving rendering to the caller. Each takes a directory in the Git working tree to
+//! compare, so neither depends on the current directory.
+//!
+//! ```no_run
+//! use std::path::Path;
+//! use whats_changed::{CompareOptions, compare_revisions};
+//!
+//! let repo = Path::new("path/to/repo");
+//! let reports = compare_revisions(repo, &CompareOptions::default(), "v1.0.0", None).unwrap();
+//! for report in reports {
+//!     for change in report.changes {
+//!         println!("{}: {} {}", report.path.display(), change.name, change.kind.as_str());
+//!     }
+//! }
+//! ```
+
+use anyhow::{Result, anyhow, bail, ensure};
+use elaborate::std::{fs::read_to_string_wc, path::PathContext, process::CommandContext};
+use semver::{BuildMetadata, Comparator, Op, Version, VersionReq};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::identity,
+    env::var_os,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::LazyLock,
+};
+
+#[doc(hidden)]
+pub mod cli;
+
+/// The kind of a dependency table
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DepKind {
+    Normal,
+    Dev,
+    Build,
+    /// Dependencies declared in `[workspace.dependencies]`; not selectable with `--kinds`
+    Workspace,
+}
+
+impl DepKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Dev => "dev",
+            Self::Build => "build",
+            Self::Workspace => "workspace",
+        }
+    }
+
+    pub fn table_name(self) -> &'static str {
+        match self {
+            Self::Normal | Self::Workspace => "dependencies",
+            Self::Dev => "dev-dependencies",
+            Self::Build => "build-dependencies",
+        }
+    }
+}
+
+/// Options controlling which dependencies `compare_revisions` compares and reports
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompareOptions {
+    /// The kinds of dependency tables to compare. `[workspace.dependencies]` tables are always
+    /// compared.
+    pub kinds: BTreeSet<DepKind>,
+    /// Whether to report dependencies added since the previous revision
+    pub added: bool,
+    /// The paths, relative to the repository root, of the manifests to compare (e.g., as returned
+    /// by `workspace_manifests`), or `None` to compare every Cargo.toml file in the repository
+    ///
+    /// If this is `Some`, deleted manifests are not reported, as there is no telling whether they
+    /// would have been selected.
+    pub manifests: Option<BTreeSet<String>>,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            kinds: BTreeSet::from([DepKind::Normal]),
+            added: false,
+            manifests: None,
+        }
+    }
+}
+
+/// A dependency table within a manifest, e.g., `[target.'cfg(unix)'.dev-dependencies]`
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepsTable {
+    pub target: Option<String>,
+    pub kind: DepKind,
+}
+
+impl DepsTable {
+    fn path(&self) -> Vec<&str> {
+        let mut path = Vec::new();
+        if self.kind == DepKind::Workspace {
+            path.push("workspace");
+        }
+        if let Some(target) = &self.target {
+            path.extend(["target", target]);
+        }
+        path.push(self.kind.table_name());
+        path
+    }
+
+    /// Returns the table's header as it would appear in a manifest, but without the brackets
+    pub fn label(&self) -> String {
+        self.path()
+            .into_iter()
+            .map(format_key)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// The changes found in one manifest
+#[derive(Debug)]
+pub struct ManifestReport {
+    pub path: PathBuf,
+    /// The manifest's path in the previous revision, which differs from `path` if the manifest was
+    /// moved
+    pub prev_path: PathBuf,
+    /// Whether the manifest was deleted, in which case every dependency is reported as removed
+    pub deleted: bool,
+    /// Whether the manifest has no counterpart in the previous revision, in which case its
+    /// dependencies are reported as added if `CompareOptions::added` is set, and are not compared
+    /// otherwise
+    pub added: bool,
+    pub changes: Vec<DependencyChange>,
+    pub errors: Vec<CompareError>,
+}
+
+impl ManifestReport {
+    fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            prev_path: path.to_path_buf(),
+            deleted: false,
+            added: false,
+            changes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.deleted && self.changes.is_empty() && self.errors.is_empty()
+    }
+
+    /// Returns the manifest's path, annotated if the manifest was added, moved, or deleted
+    ///
+    /// `quote` is written before and after each path.
+    pub fn describe(&self, quote: &str) -> String {
+        let path = self.path.display();
+        if self.deleted {
+            format!("{quote}{path}{quote} (deleted)")
+        } else if self.added {
+            format!("{quote}{path}{quote} (added)")
+        } else if self.prev_path == self.path {
+            format!("{quote}{path}{quote}")
+        } else {
+            let prev_path = self.prev_path.display();
+            format!("{quote}{path}{quote} (moved from {quote}{prev_path}{quote})")
+        }
+    }
+}
+
+/// A change to one dependency in a manifest
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DependencyChange {
+    pub table: DepsTable,
+    pub name: String,
+    /// The dependency's name in the previous revision, which differs from `name` if the dependency
+    /// was renamed
+    pub prev_name: String,
+    pub kind: ChangeKind,
+    pub req_prev: Option<VersionReq>,
+    pub req_curr: Option<VersionReq>,
+    /// The previous requirement as written in the manifest, e.g., `1.2` rather than `^1.2`
+    pub req_prev_verbatim: Option<String>,
+    /// The current requirement as written in the manifest
+    pub req_curr_verbatim: Option<String>,
+    pub source_prev: Option<DepSource>,
+    pub source_curr: Option<DepSource>,
+    /// The features added or removed, for `FeaturesAdded` and `FeaturesRemoved` changes
+    pub features: Vec<String>,
+    /// The size of the change, for `Upgraded` and `Downgraded` changes
+    pub bump: Option<Bump>,
+    /// The least version the current requirement admits, for `Added` changes
+    pub minimum_version: Option<Version>,
+}
+
+/// The kind of a `DependencyChange` or `LockfileChange`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangeKind {
+    Upgraded,
+    Downgraded,
+    Removed,
+    Added,
+    /// The dependency switched from an explicit entry to `workspace = true`
+    Inherited,
+    /// The dependency switched from `workspace = true` to an explicit entry
+    NoLongerInherited,
+    /// The dependency's name changed, but its package did not
+    Renamed,
+    /// The dependency switched between registry, git, and path sources
+    SourceChanged,
+    /// The git dependency's repository URL changed
+    GitUrlChanged,
+    /// The git dependency's rev, tag, or branch changed
+    GitReferenceChanged,
+    /// The dependency switched between crates.io and an alternate registry, or between alternate
+    /// registries
+    RegistryChanged,
+    FeaturesAdded,
+    FeaturesRemoved,
+    DefaultFeaturesEnabled,
+    DefaultFeaturesDisabled,
+    MadeOptional,
+    NoLongerOptional,
+}
+
+impl DependencyChange {
+    /// Returns true if the dependency is a path dependency, i.e., a crate in the same repository
+    ///
+    /// If the dependency was removed, its previous source is used.
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self.source_curr.as_ref().or(self.source_prev.as_ref()),
+            Some(DepSource::Path { .. })
+        )
+    }
+
+    /// Returns the name of the dependency's alternate registry, or `None` if the dependency is from
+    /// crates.io or is not from a registry
+    ///
+    /// If the dependency was removed, its previous source is used.
+    pub fn registry(&self) -> Option<&str> {
+        match self.source_curr.as_ref().or(self.source_prev.as_ref()) {
+            Some(DepSource::Registry { name }) => name.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Upgraded => "upgraded",
+            Self::Downgraded => "downgraded",
+            Self::Removed => "removed",
+            Self::Added => "added",
+            Self::Inherited => "inherited",
+            Self::NoLongerInherited => "no-longer-inherited",
+            Self::Renamed => "renamed",
+            Self::SourceChanged => "source-changed",
+            Self::GitUrlChanged => "git-url-changed",
+            Self::GitReferenceChanged => "git-reference-changed",
+            Self::RegistryChanged => "registry-changed",
+            Self::FeaturesAdded => "features-added",
+            Self::FeaturesRemoved => "features-removed",
+            Self::DefaultFeaturesEnabled => "default-features-enabled",
+            Self::DefaultFeaturesDisabled => "default-features-disabled",
+            Self::MadeOptional => "made-optional",
+            Self::NoLongerOptional => "no-longer-optional",
+        }
+    }
+}
+
+/// The size of an upgrade or downgrade, and whether it is semver-breaking
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Bump {
+    pub level: BumpLevel,
+    pub breaking: bool,
+}
+
+/// The leftmost version component that changed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BumpLevel {
+    Major,
+    Minor,
+    Patch,
+}
+
+impl BumpLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Patch => "patch",
+        }
+    }
+}
+
+/// Where a dependency comes from
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DepSource {
+    /// crates.io if `name` is `None`, otherwise the alternate registry `name`
+    Registry {
+        name: Option<String>,
+    },
+    Git {
+        url: String,
+        reference: Option<GitReference>,
+    },
+    Path {
+        path: String,
+    },
+}
+
+/// The rev, tag, or branch of a git dependency
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GitReference {
+    Rev(String),
+    Tag(String),
+    Branch(String),
+}
+
+impl DepSource {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Registry { .. } => "registry",
+            Self::Git { .. } => "git",
+            Self::Path { .. } => "path",
+        }
+    }
+}
+
+impl std::fmt::Display for DepSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Registry { name: None } => write!(f, "crates.io"),
+            Self::Registry { name: Some(name) } => write!(f, "registry `{name}`"),
+            Self::Git { url, reference } => {
+                write!(f, "git {url}")?;
+                if let Some(reference) = reference {
+                    write!(f, " ({reference})")?;
+                }
+                Ok(())
+            }
+            Self::Path { path } => write!(f, "path {path}"),
+        }
+    }
+}
+
+impl GitReference {
+    /// Returns the key used to specify the reference in a manifest
+    pub fn key(&self) -> &'static str {
+        match self {
+            Self::Rev(_) => "rev",
+            Self::Tag(_) => "tag",
+            Self::Branch(_) => "branch",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Rev(value) | Self::Tag(value) | Self::Branch(value) => value,
+        }
+    }
+
+    /// Returns how the reference is referred to in messages, e.g., "`foo` git rev changed from ..."
+    pub fn noun(&self) -> &'static str {
+        match self {
+            Self::Rev(_) => "git rev",
+            Self::Tag(_) => "tag",
+            Self::Branch(_) => "branch",
+        }
+    }
+}
+
+impl std::fmt::Display for GitReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.key(), self.value())
+    }
+}
+
+/// The changes to the packages locked by a Cargo.lock file
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockfileReport {
+    pub path: PathBuf,
+    pub changes: Vec<LockfileChange>,
+}
+
+/// A change to the locked versions of one package
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockfileChange {
+    pub name: String,
+    /// The package's `source` as recorded in the lockfile, e.g., `registry+https://...`, or `None`
+    /// if the package is from crates.io or has no source (e.g., is a workspace member)
+    ///
+    /// As in manifests, a package's source is part of its identity, so the same package from two
+    /// sources is reported as two packages.
+    pub source: Option<String>,
+    pub kind: ChangeKind,
+    pub version_prev: Option<Version>,
+    pub version_curr: Option<Version>,
+    pub bump: Option<Bump>,
+}
+
+/// An error comparing one dependency, which does not prevent the others from being compared
+#[derive(Debug)]
+pub struct CompareError {
+    pub table: DepsTable,
+    pub name: String,
+    pub error: anyhow::Error,
+}
+
+/// A manifest, along with the `[workspace.dependencies]` table its `workspace = true` dependencies
+/// are resolved against
+#[derive(Clone, Copy)]
+struct Manifest<'a> {
+    table: &'a toml::Table,
+    workspace_deps: Option<&'a toml::Table>,
+}
+
+/// A dependency table with its inherited dependencies resolved
+struct ResolvedDeps {
+    deps: toml::Table,
+    /// Names of the dependencies that were inherited from the workspace
+    inherited: BTreeSet<String>,
+}
+
+/// A dependency matched between the previous and current revisions
+struct MatchedDep<'a> {
+    table: &'a DepsTable,
+    name_prev: &'a str,
+    name_curr: &'a str,
+    value_prev: &'a toml::Value,
+    value_curr: &'a toml::Value,
+}
+
+impl MatchedDep<'_> {
+    /// Returns a change of kind `kind` to the dependency
+    ///
+    /// The version requirements are informational only, so failing to get them is not an error.
+    fn change(&self, kind: ChangeKind) -> DependencyChange {
+        DependencyChange {
+            table: self.table.clone(),
+            name: self.name_curr.to_owned(),
+            prev_name: self.name_prev.to_owned(),
+            kind,
+            req_prev: get_req_from_value(self.value_prev).ok().flatten(),
+            req_curr: get_req_from_value(self.value_curr).ok().flatten(),
+            req_prev_verbatim: get_req_str_from_value(self.value_prev).map(ToOwned::to_owned),
+            req_curr_verbatim: get_req_str_from_value(self.value_curr).map(ToOwned::to_owned),
+            source_prev: get_source_from_value(self.value_prev),
+            source_curr: get_source_from_value(self.value_curr),
+            features: Vec::new(),
+            bump: None,
+            minimum_version: None,
+        }
+    }
+}
+
+/// Returns the most recent tag reachable from `HEAD` in the Git repository containing `repo`
+pub fn most_recent_tag(repo: &Path) -> Result<String> {
+    let mut command = Command::new("git");
+    command
+        .current_dir(repo)
+        .args(["describe", "--tags", "--abbrev=0"]);
+    let output = command.output_wc()?;
+    ensure!(
+        output.status.success(),
+        "no previous tag found; specify a revision explicitly"
+    );
+    let stdout = std::str::from_utf8(&output.stdout)?;
+    let tag = stdout.trim().to_string();
+    Ok(tag)
+}
+
+/// Compares the manifests in `prev_rev` to those in `curr_rev`, or in the working tree if
+/// `curr_rev` is `None`
+///
+/// The manifests are all of those in the Git working tree containing `repo`, which may be any
+/// directory in the working tree. The reports' paths are relative to the working tree's root.
+pub fn compare_revisions(
+    repo: &Path,
+    options: &CompareOptions,
+    prev_rev: &str,
+    curr_rev: Option<&str>,
+) -> Result<Vec<ManifestReport>> {
+    // smoelius: Git resolves `<rev>:<path>` and reports renames relative to the root, so the root
+    // is used throughout.
+    let repo = &repository_root(repo)?;
+    let tree_prev = Tree::new(repo, Some(prev_rev))?;
+    let tree_curr = Tree::new(repo, curr_rev)?;
+    let mut moved = MovedManifests::new(prev_rev, &tree_prev, &tree_curr)?;
+    let mut reports = Vec::new();
+    let mut paired_prev = BTreeSet::new();
+    for path_curr_str in &tree_curr.manifests {
+        if options
+            .manifests
+            .as_ref()
+            .is_some_and(|manifests| !manifests.contains(path_curr_str))
+        {
+            continue;
+        }
+        let path_curr = Path::new(path_curr_str);
+        let manifest_curr = tree_curr.read_manifest(path_curr_str)?;
+        let path_prev_str = if tree_prev.manifests.contains(path_curr_str) {
+            path_curr_str.clone()
+        } else if let Some(path_prev_str) = moved.take(path_curr_str, &manifest_curr) {
+            path_prev_str
+        } else {
+            // smoelius: A manifest with no previous counterpart is new. If added dependencies are
+            // to be reported, compare it to an empty manifest, as is done for deleted manifests
+            // below, so that each of its dependencies is reported as added.
+            let mut report = if options.added {
+                let workspace_deps_curr =
+                    tree_curr.workspace_deps(path_curr_str, &manifest_curr)?;
+                compare_manifests(
+                    options,
+                    path_curr,
+                    Manifest {
+                        table: &toml::Table::new(),
+                        workspace_deps: None,
+                    },
+                    Manifest {
+                        table: &manifest_curr,
+                        workspace_deps: workspace_deps_curr.as_ref(),
+                    },
+                )
+            } else {
+                ManifestReport::new(path_curr)
+            };
+            report.added = true;
+            reports.push(report);
+            continue;
+        };
+        let manifest_prev = tree_prev.read_manifest(&path_prev_str)?;
+        let workspace_deps_prev = tree_prev.workspace_deps(&path_prev_str, &manifest_prev)?;
+        let workspace_deps_curr = tree_curr.workspace_deps(path_curr_str, &manifest_curr)?;
+        let mut report = compare_manifests(
+            options,
+            path_curr,
+            Manifest {
+                table: &manifest_prev,
+                workspace_deps: workspace_deps_prev.as_ref(),
+            },
+            Manifest {
+                table: &manifest_curr,
+                workspace_deps: workspace_deps_curr.as_ref(),
+            },
+        );
+        report.prev_path = PathBuf::from(&path_prev_str);
+        reports.push(report);
+        paired_prev.insert(path_prev_str);
+    }
+    if options.manifests.is_some() {
+        return Ok(reports);
+    }
+    // smoelius: Any previous manifest not paired with a current one was deleted. Compare it to an
+    // empty manifest so that the dependencies it took with it are reported as removed.
+    for path_prev_str in tree_prev.manifests.difference(&paired_prev) {
+        let manifest_prev = tree_prev.read_manifest(path_prev_str)?;
+        let workspace_deps_prev = tree_prev.workspace_deps(path_prev_str, &manifest_prev)?;
+        let mut report = compare_manifests(
+            options,
+            Path::new(path_prev_str),
+            Manifest {
+                table: &manifest_prev,
+                workspace_deps: workspace_deps_prev.as_ref(),
+            },
+            Manifest {
+                table: &toml::Table::new(),
+                workspace_deps: None,
+            },
+        );
+        report.deleted = true;
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+/// Returns the paths, relative to the repository root, of the manifests of the selected members of
+/// the workspace, as determined by `cargo metadata --no-deps`
+///
+/// The workspace is the one containing `manifest_path`, or `dir` if `manifest_path` is `None`. A
+/// relative `manifest_path` is relative to `dir`. Members are selected as by Cargo's `--package`
+/// and `--workspace` options: the members named in `packages` if any, else every member if
+/// `workspace` is true, else the package whose manifest is `manifest_path` (or is nearest `dir`),
+/// else the default members. When more than the named or current package is selected, the workspace root's
+/// manifest is included too, so that its `[workspace.dependencies]` table is compared.
+///
+/// As with Cargo, naming packages and passing `workspace` are mutually exclusive.
+pub fn workspace_manifests(
+    dir: &Path,
+    manifest_path: Option<&Path>,
+    packages: &[String],
+    workspace: bool,
+) -> Result<BTreeSet<String>> {
+    ensure!(
+        packages.is_empty() || !workspace,
+        "`--package` cannot be used with `--workspace`"
+    );
+    let mut command = Command::new(var_os("CARGO").unwrap_or_else(|| "cargo".into()));
+    command
+        .current_dir(dir)
+        .args(["metadata", "--no-deps", "--format-version=1"]);
+    if let Some(manifest_path) = manifest_path {
+        command.arg("--manifest-path").arg(manifest_path);
+    }
+    let output = command.output_wc()?;
+    ensure!(output.status.success(), "command failed: {command:?}");
+    let metadata = serde_json::from_slice::<serde_json::Value>(&output.stdout)?;
+    let ids = |key: &str| {
+        metadata[key]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(serde_json::Value::as_str)
+            .collect::<BTreeSet<_>>()
+    };
+    let member_ids = ids("workspace_members");
+    // smoelius: `workspace_default_members` was added in Cargo 1.71. Older versions treat every
+    // member as a default member.
+    let default_member_ids = if metadata.get("workspace_default_members").is_some() {
+        ids("workspace_default_members")
+    } else {
+        member_ids.clone()
+    };
+    // smoelius: Each member is a triple of its id, name, and manifest path. The manifest paths are
+    // canonicalized, as is the current manifest path below, so that they can be compared even if
+    // one is reached through a symlink.
+    let mut members = Vec::new();
+    for package in metadata["packages"].as_array().into_iter().flatten() {
+        let (Some(id), Some(name), Some(manifest_path)) = (
+            package["id"].as_str(),
+            package["name"].as_str(),
+            package["manifest_path"].as_str(),
+        ) else {
+            bail!("unexpected `cargo metadata` output: {package}");
+        };
+        if member_ids.contains(id) {
+            members.push((id, name, Path::new(manifest_path).canonicalize_wc()?));
+        }
+    }
+    let Some(workspace_root) = metadata["workspace_root"].as_str() else {
+        bail!("`cargo metadata` output has no `workspace_root`");
+    };
+    let root_manifest_path = Path::new(workspace_root)
+        .join("Cargo.toml")
+        .canonicalize_wc()?;
+    let current_manifest_path = if let Some(manifest_path) = manifest_path {
+        Some(dir.join(manifest_path).canonicalize_wc()?)
+    } else {
+        dir.canonicalize_wc()?
+            .ancestors()
+            .map(|dir| dir.join("Cargo.toml"))
+            .find(|path| path.exists())
+    };
+    let current_member = members
+        .iter()
+        .find(|(_, _, manifest_path)| current_manifest_path.as_ref() == Some(manifest_path));
+    let mut selected = Vec::new();
+    if !packages.is_empty() {
+        for package in packages {
+            let Some((_, _, manifest_path)) = members.iter().find(|(_, name, _)| name == package)
+            else {
+                bail!("package `{package}` is not a member of the workspace");
+            };
+            selected.push(manifest_path);
+        }
+    } else if workspace {
+        selected.extend(members.iter().map(|(_, _, manifest_path)| manifest_path));
+        selected.push(&root_manifest_path);
+    } else if let Some((_, _, manifest_path)) = current_member {
+        selected.push(manifest_path);
+    } else {
+        selected.extend(
+            members
+                .iter()
+                .filter(|(id, _, _)| default_member_ids.contains(id))
+                .map(|(_, _, manifest_path)| manifest_path),
+        );
+        selected.push(&root_manifest_path);
+    }
+    let toplevel = repository_root(dir)?;
+    selected
+        .into_iter()
+        .map(|manifest_path| {
+            let relative_path = manifest_path.strip_prefix_wc(&toplevel)?;
+            let Some(relative_path) = relative_path.to_str() else {
+                bail!("path is not valid UTF-8: {}", relative_path.display());
+            };
+            Ok(relative_path.to_owned())
+        })
+        .collect()
+}
+
+/// Returns the canonical path of the root of the Git working tree containing `dir`
+pub fn repository_root(dir: &Path) -> Result<PathBuf> {
+    let mut command = Command::new("git");
+    command
+        .current_dir(dir)
+        .args(["rev-parse", "--show-toplevel"]);
+    let output = command.output_wc()?;
+    ensure!(output.status.success(), "command failed: {command:?}");
+    let stdout = String::from_utf8(output.stdout)?;
+    Path::new(stdout.trim_end()).canonicalize_wc()
+}
+
+/// Compares the Cargo.lock files in `prev_rev` to those in `curr_rev`, or in the working tree if
+/// `curr_rev` is `None`
+///
+/// The lockfiles are all of those in the Git working tree containing `repo`, as for
+/// `compare_revisions`. A lockfile that exists in only
+/// one revision is compared to an empty one, so that all of its packages are reported as added or
+/// removed.
+pub fn compare_lockfiles(
+    repo: &Path,
+    prev_rev: &str,
+    curr_rev: Option<&str>,
+) -> Result<Vec<LockfileReport>> {
+    let repo = &repository_root(repo)?;
+    let tree_prev = Tree::new(repo, Some(prev_rev))?;
+    let tree_curr = Tree::new(repo, curr_rev)?;
+    let mut reports = Vec::new();
+    for path in tree_prev.lockfiles.union(&tree_curr.lockfiles) {
+        let versions_prev = tree_prev.locked_versions(path)?;
+        let versions_curr = tree_curr.locked_versions(path)?;
+        reports.push(LockfileReport {
+            path: PathBuf::from(path),
+            changes: compare_locked_versions(&versions_prev, &versions_curr),
+        });
+    }
+    Ok(reports)
+}
+
+fn compare_locked_versions(
+    versions_prev: &LockedVersions,
+    versions_curr: &LockedVersions,
+) -> Vec<LockfileChange> {
+    let mut changes = Vec::new();
+    let keys = versions_prev
+        .keys()
+        .chain(versions_curr.keys())
+        .collect::<BTreeSet<_>>();
+    let none = BTreeSet::new();
+    for key in keys {
+        let (name, source) = key;
+        let versions_prev = versions_prev.get(key).unwrap_or(&none);
+        let versions_curr = versions_curr.get(key).unwrap_or(&none);
+        let removed = versions_prev.difference(versions_curr).collect::<Vec<_>>();
+        let added = versions_curr.difference(versions_prev).collect::<Vec<_>>();
+        // smoelius: A lockfile may contain several versions of a package (e.g., `syn` 1 and 2). If
+        // exactly one version was replaced by exactly one other, the package was upgraded or
+        // downgraded. Otherwise, each version is reported as added or removed individually.
+        if let ([version_prev], [version_curr]) = (&removed[..], &added[..]) {
+            let kind = if version_curr < version_prev {
+                ChangeKind::Downgraded
+            } else {
+                ChangeKind::Upgraded
+            };
+            changes.push(LockfileChange {
+                name: name.clone(),
+                source: source.clone(),
+                kind,
+                version_prev: Some((*version_prev).clone()),
+                version_curr: Some((*version_curr).clone()),
+                bump: Some(classify_bump(version_prev, version_curr)),
+            });
+            continue;
+        }
+        for version in removed {
+            changes.push(LockfileChange {
+                name: name.clone(),
+                source: source.clone(),
+                kind: ChangeKind::Removed,
+                version_prev: Some(version.clone()),
+                version_curr: None,
+                bump: None,
+            });
+        }
+        for version in added {
+            changes.push(LockfileChange {
+                name: name.clone(),
+                source: source.clone(),
+                kind: ChangeKind::Added,
+                version_prev: None,
+                version_curr: Some(version.clone()),
+                bump: None,
+            });
+        }
+    }
+    changes
+}
+
+/// Map from package name and source (as in `LockfileChange`) to the versions locked
+type LockedVersions = BTreeMap<(String, Option<String>), BTreeSet<Version>>;
+
+/// The sources Cargo records for packages from crates.io, via its git and sparse indexes
+const CRATES_IO_SOURCES: [&str; 2] = [
+    "registry+https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+/// The manifests and lockfiles in a revision, or in the working tree
+struct Tree<'a> {
+    repo: &'a Path,
+    /// `None` means the working tree
+    rev: Option<&'a str>,
+    manifests: BTreeSet<String>,
+    lockfiles: BTreeSet<String>,
+}
+
+impl<'a> Tree<'a> {
+    fn new(repo: &'a Path, rev: Option<&'a str>) -> Result<Self> {
+        let mut manifests = BTreeSet::new();
+        let mut lockfiles = BTreeSet::new();
+        for path in list_files(repo, rev)? {
+            if is_manifest(&path)? {
+                manifests.insert(path);
+            } else if is_lockfile(&path)? {
+                lockfiles.insert(path);
+            }
+        }
+        Ok(Self {
+            repo,
+            rev,
+            manifests,
+            lockfiles,
+        })
+    }
+
+    fn read_manifest(&self, path: &str) -> Result<toml::Table> {
+        if let Some(rev) = self.rev {
+            let Some(contents) = show_file(self.repo, rev, path)? else {
+                bail!("`{path}` does not exist in `{rev}`");
+            };
+            contents.parse::<toml::Table>().map_err(Into::into)
+        } else {
+            read_manifest(self.repo.join(path))
+        }
+    }
+
+    /// Returns the versions of each package locked by the lockfile at `path`, or an empty map if
+    /// there is no such lockfile
+    fn locked_versions(&self, path: &str) -> Result<LockedVersions> {
+        if !self.lockfiles.contains(path) {
+            return Ok(BTreeMap::new());
+        }
+        let contents = if let Some(rev) = self.rev {
+            let Some(contents) = show_file(self.repo, rev, path)? else {
+                bail!("`{path}` does not exist in `{rev}`");
+            };
+            contents
+        } else {
+            read_to_string_wc(self.repo.join(path))?
+        };
+        let lockfile = contents.parse::<toml::Table>()?;
+        let packages = lockfile
+            .get("package")
+            .and_then(toml::Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut versions = LockedVersions::new();
+        for package in packages {
+            let get_str = |key: &str| package.get(key).and_then(toml::Value::as_str);
+            let (Some(name), Some(version)) = (get_str("name"), get_str("version")) else {
+                bail!("`{path}` contains a malformed package: {package}");
+            };
+            let source = get_str("source")
+                .filter(|source| !CRATES_IO_SOURCES.contains(source))
+                .map(ToOwned::to_owned);
+            versions
+                .entry((name.to_owned(), source))
+                .or_default()
+                .insert(version.parse()?);
+        }
+        Ok(versions)
+    }
+
+    /// Returns the `[workspace.dependencies]` table of the workspace enclosing the manifest at
+    /// `path`, or `None` if there is no such workspace
+    ///
+    /// The enclosing workspace is the nearest manifest with a `[workspace]` table, starting with
+    /// the manifest itself and proceeding through its ancestor directories.
+    fn workspace_deps(&self, path: &str, manifest: &toml::Table) -> Result<Option<toml::Table>> {
+        for dir in Path::new(path).parent_wc()?.ancestors() {
+            let root_path = dir.join("Cargo.toml");
+            let Some(root_path_str) = root_path.to_str() else {
+                continue;
+            };
+            let root = if root_path_str == path {
+                manifest.clone()
+            } else if self.manifests.contains(root_path_str) {
+                self.read_manifest(root_path_str)?
+            } else {
+                continue;
+            };
+            let Some(workspace) = root.get("workspace").and_then(|value| value.as_table()) else {
+                continue;
+            };
+            let deps = workspace
+                .get("dependencies")
+                .and_then(|value| value.as_table())
+                .cloned()
+                .unwrap_or_default();
+            return Ok(Some(deps));
+        }
+        Ok(None)
+    }
+}
+
+/// Manifests in the previous revision with no counterpart at the same path in the current revision,
+/// i.e., candidates for manifests that were moved
+struct MovedManifests {
+    /// Map from current path to previous path, for manifests Git detects as renamed
+    renames: BTreeMap<String, String>,
+    /// Map from package name to previous path
+    package_names: BTreeMap<String, String>,
+}
+
+impl MovedManifests {
+    fn new(prev_rev: &str, tree_prev: &Tree, tree_curr: &Tree) -> Result<Self> {
+        let renames = manifest_renames(tree_curr.repo, prev_rev, tree_curr.rev)?;
+        let mut package_names = BTreeMap::new();
+        for path_prev_str in tree_prev.manifests.difference(&tree_curr.manifests) {
+            let manifest_prev = tree_prev.read_manifest(path_prev_str)?;
+            if let Some(name) = package_name(&manifest_prev) {
+                package_names.insert(name.to_owned(), path_prev_str.clone());
+            }
+        }
+        Ok(Self {
+            renames,
+            package_names,
+        })
+    }
+
+    /// Returns the previous path of the manifest at `path_curr_str`, if it was moved
+    ///
+    /// Git's rename detection is consulted first. If Git does not consider the manifest renamed
+    /// (e.g., because its contents changed too much), a previous manifest with the same package
+    /// name is used. Either way, the previous path is not returned again.
+    fn take(&mut self, path_curr_str: &str, manifest_curr: &toml::Table) -> Option<String> {
+        let path_prev_str = self.renames.remove(path_curr_str).or_else(|| {
+            package_name(manifest_curr).and_then(|name| self.package_names.remove(name))
+        })?;
+        self.renames.retain(|_, other| *other != path_prev_str);
+        self.package_names
+            .retain(|_, other| *other != path_prev_str);
+        Some(path_prev_str)
+    }
+}
+
+/// Returns a map from current path to previous path, for manifests Git detects as renamed
+fn manifest_renames(
+    repo: &Path,
+    prev_rev: &str,
+    curr_rev: Option<&str>,
+) -> Result<BTreeMap<String, String>> {
+    let mut command = Command::new("git");
+    command.current_dir(repo).args([
+        "diff",
+        "--name-status",
+        "--find-renames",
+        "--diff-filter=R",
+        prev_rev,
+    ]);
+    if let Some(curr_rev) = curr_rev {
+        command.arg(curr_rev);
+    }
+    let output = command.output_wc()?;
+    ensure!(output.status.success(), "command failed: {command:?}");
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut renames = BTreeMap::new();
+    for line in stdout.lines() {
+        // smoelius: Each line has the form `R<score>\t<previous path>\t<current path>`.
+        let [_, path_prev_str, path_curr_str] = line.split('\t').collect::<Vec<_>>()[..] else {
+            bail!("unexpected `git diff` output: {line}");
+        };
+        if is_manifest(path_prev_str)? && is_manifest(path_curr_str)? {
+            renames.insert(path_curr_str.to_owned(), path_prev_str.to_owned());
+        }
+    }
+    Ok(renames)
+}
+
+fn package_name(manifest: &toml::Table) -> Option<&str> {
+    manifest
+        .get("package")
+        .and_then(|value| value.as_table())
+        .and_then(|table| table.get("name"))
+        .and_then(|value| value.as_str())
+}
+
+fn is_manifest(path: &str) -> Result<bool> {
+    Ok(Path::new(path).file_name_wc()? == "Cargo.toml")
+}
+
+fn is_lockfile(path: &str) -> Result<bool> {
+    Ok(Path::new(path).file_name_wc()? == "Cargo.lock")
+}
+
+/// Returns the paths of the files in `rev`, or of the files in the index if `rev` is `None`
+fn list_files(repo: &Path, rev: Option<&str>) -> Result<Vec<String>> {
+    let mut command = Command::new("git");
+    command.current_dir(repo);
+    if let Some(rev) = rev {
+        command.args(["ls-tree", "-r", "--name-only", rev]);
+    } else {
+        command.args(["ls-files"]);
+    }
+    let output = command.output_wc()?;
+    ensure!(output.status.success(), "command failed: {command:?}");
+    let stdout = String::from_utf8(output.stdout)?;
+    Ok(stdout.lines().map(ToOwned::to_owned).collect())
+}
+
+/// Returns the contents of `path` in `rev`, or `None` if `path` does not exist in `rev`
+fn show_file(repo: &Path, rev: &str, path: &str) -> Result<Option<String>> {
+    let mut command = Command::new("git");
+    command
+        .current_dir(repo)
+        .args(["show", &format!("{rev}:{path}")]);
+    let output = command.output_wc()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let contents = String::from_utf8(output.stdout)?;
+    Ok(Some(contents))
+}
+
+fn read_manifest(manifest_path: impl AsRef<Path>) -> Result<toml::Table> {
+    let contents = read_to_string_wc(manifest_path)?;
+    contents.parse::<toml::Table>().map_err(Into::into)
+}
+
+fn compare_manifests(
+    options: &CompareOptions,
+    path_curr: &Path,
+    manifest_prev: Manifest,
+    manifest_curr: Manifest,
+) -> ManifestReport {
+    let mut report = ManifestReport::new(path_curr);
+    for table in deps_tables(&options.kinds, manifest_prev.table, manifest_curr.table) {
+        let deps_prev = resolve_inherited_deps(
+            get_deps_table(manifest_prev.table, &table),
+            manifest_prev.workspace_deps,
+        );
+        let deps_curr = resolve_inherited_deps(
+            get_deps_table(manifest_curr.table, &table),
+            manifest_curr.workspace_deps,
+        );
+        compare_deps_tables(options, &mut report, &table, &deps_prev, &deps_curr);
+    }
+    report
+}
+
+/// Replaces each `workspace = true` dependency in `deps` with the corresponding entry in
+/// `workspace_deps`, merged with the dependency's own keys (e.g., `features`)
+///
+/// Dependencies that cannot be resolved (e.g., because `workspace_deps` is `None`) are left as is.
+fn resolve_inherited_deps(
+    deps: &toml::Table,
+    workspace_deps: Option<&toml::Table>,
+) -> ResolvedDeps {
+    let mut resolved = ResolvedDeps {
+        deps: deps.clone(),
+        inherited: BTreeSet::new(),
+    };
+    for (name, value) in &mut resolved.deps {
+        let Some(table) = value.as_table() else {
+            continue;
+        };
+        if !table
+            .get("workspace")
+            .and_then(toml::Value::as_bool)
+            .is_some_and(identity)
+        {
+            continue;
+        }
+        let Some(workspace_value) = workspace_deps.and_then(|deps| deps.get(name)) else {
+            continue;
+        };
+        let mut inherited = match workspace_value {
+            toml::Value::String(_) => {
+                toml::Table::from_iter([("version".to_owned(), workspace_value.clone())])
+            }
+            toml::Value::Table(workspace_table) => workspace_table.clone(),
+            _ => continue,
+        };
+        for (key, value) in table {
+            match (key.as_str(), inherited.get_mut(key), value) {
+                ("workspace", _, _) => {}
+                // smoelius: Features are additive.
+                ("features", Some(toml::Value::Array(features)), toml::Value::Array(more)) => {
+                    features.extend(more.iter().cloned());
+                }
+                (_, _, _) => {
+                    inherited.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        *value = toml::Value::Table(inherited);
+        resolved.inherited.insert(name.clone());
+    }
+    resolved
+}
+
+/// Returns the dependency tables to compare in each manifest
+///
+/// Target-specific tables (e.g., `[target.'cfg(unix)'.dependencies]`) are included for every
+/// target that appears in either manifest, so that dependencies are matched by target and name.
+fn deps_tables(
+    kinds: &BTreeSet<DepKind>,
+    manifest_prev: &toml::Table,
+    manifest_curr: &toml::Table,
+) -> Vec<DepsTable> {
+    let mut tables = kinds
+        .iter()
+        .map(|&kind| DepsTable { target: None, kind })
+        .collect::<Vec<_>>();
+    let targets = [manifest_prev, manifest_curr]
+        .into_iter()
+        .filter_map(|manifest| manifest.get("target").and_then(|value| value.as_table()))
+        .flat_map(toml::Table::keys)
+        .collect::<BTreeSet<_>>();
+    for target in targets {
+        for &kind in kinds {
+            tables.push(DepsTable {
+                target: Some(target.clone()),
+                kind,
+            });
+        }
+    }
+    tables.push(DepsTable {
+        target: None,
+        kind: DepKind::Workspace,
+    });
+    tables
+}
+
+/// Formats `key` as it would appear in a TOML table header
+fn format_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        key.to_owned()
+    } else if !key.contains('\'') {
+        format!("'{key}'")
+    } else {
+        format!("{key:?}")
+    }
+}
+
+fn get_deps_table<'a>(manifest: &'a toml::Table, table: &DepsTable) -> &'a toml::Table {
+    static EMPTY: LazyLock<toml::Table> = LazyLock::new(toml::Table::default);
+    table
+        .path()
+        .into_iter()
+        .try_fold(manifest, |table, key| {
+            table.get(key).and_then(|value| value.as_table())
+        })
+        // smoelius: Manifest has no such table.
+        .unwrap_or(&EMPTY)
+}
+
+fn compare_deps_tables(
+    options: &CompareOptions,
+    report: &mut ManifestReport,
+    table: &DepsTable,
+    deps_prev: &ResolvedDeps,
+    deps_curr: &ResolvedDeps,
+) {
+    // smoelius: Dependencies are matched by name and package. A previous dependency with no such
+    // match is then matched by package alone, i.e., it was renamed. Note that a dependency whose
+    // name stayed the same but whose package changed is a different dependency.
+    let same_package = |name: &str, value_prev: &toml::Value, value_curr: &toml::Value| {
+        dep_package(name, value_prev) == dep_package(name, value_curr)
+    };
+    let mut unmatched_curr = deps_curr
+        .deps
+        .iter()
+        .filter(|&(name_curr, value_curr)| {
+            !deps_prev
+                .deps
+                .get(name_curr)
+                .is_some_and(|value_prev| same_package(name_curr, value_prev, value_curr))
+        })
+        .collect::<BTreeMap<_, _>>();
+    for (name_prev, value_prev) in &deps_prev.deps {
+        let package = dep_package(name_prev, value_prev);
+        let name_curr = if deps_curr
+            .deps
+            .get(name_prev)
+            .is_some_and(|value_curr| same_package(name_prev, value_prev, value_curr))
+        {
+            name_prev
+        } else if let Some((&name_curr, _)) = unmatched_curr
+            .iter()
+            .find(|&(name_curr, value_curr)| dep_package(name_curr, value_curr) == package)
+        {
+            unmatched_curr.remove(name_curr);
+            name_curr
+        } else {
+            let change = DependencyChange {
+                table: table.clone(),
+                name: name_prev.clone(),
+                prev_name: name_prev.clone(),
+                kind: ChangeKind::Removed,
+                req_prev: get_req_from_value(value_prev).ok().flatten(),
+                req_curr: None,
+                req_prev_verbatim: get_req_str_from_value(value_prev).map(ToOwned::to_owned),
+                req_curr_verbatim: None,
+                source_prev: get_source_from_value(value_prev),
+                source_curr: None,
+                features: Vec::new(),
+                bump: None,
+                minimum_version: None,
+            };
+            record(report, table, name_prev, Ok(Some(change)));
+            continue;
+        };
+        let dep = MatchedDep {
+            table,
+            name_prev,
+            name_curr,
+            value_prev,
+            value_curr: &deps_curr.deps[name_curr],
+        };
+        if name_prev != name_curr {
+            record(
+                report,
+                table,
+                name_curr,
+                Ok(Some(dep.change(ChangeKind::Renamed))),
+            );
+        }
+        record(report, table, name_curr, compare_sources(&dep));
+        record(report, table, name_curr, compare_git_urls(&dep));
+        record(report, table, name_curr, compare_git_references(&dep));
+        record(report, table, name_curr, compare_registries(&dep));
+        record(report, table, name_curr, compare_deps(&dep));
+        record(
+            report,
+            table,
+            name_curr,
+            compare_features(&dep, ChangeKind::FeaturesAdded),
+        );
+        record(
+            report,
+            table,
+            name_curr,
+            compare_features(&dep, ChangeKind::FeaturesRemoved),
+        );
+        record(report, table, name_curr, compare_default_features(&dep));
+        record(report, table, name_curr, compare_optional(&dep));
+        let inherited_prev = deps_prev.inherited.contains(name_prev);
+        let inherited_curr = deps_curr.inherited.contains(name_curr);
+        if inherited_prev != inherited_curr {
+            let kind = if inherited_curr {
+                ChangeKind::Inherited
+            } else {
+                ChangeKind::NoLongerInherited
+            };
+            record(report, table, name_curr, Ok(Some(dep.change(kind))));
+        }
+    }
+    if options.added {
+        for (name_curr, value_curr) in unmatched_curr {
+            let result = describe_added_dep(table, name_curr, value_curr);
+            record(report, table, name_curr, result);
+        }
+    }
+}
+
+/// Returns the name of the package `name` refers to, which differs from `name` if the dependency
+/// has a `package` key
+fn dep_package<'a>(name: &'a str, value: &'a toml::Value) -> &'a str {
+    value
+        .as_table()
+        .and_then(|table| table.get("package"))
+        .and_then(|value| value.as_str())
+        .unwrap_or(name)
+}
+
+fn record(
+    report: &mut ManifestReport,
+    table: &DepsTable,
+    name: &str,
+    result: Result<Option<DependencyChange>>,
+) {
+    match result {
+        Ok(None) => {}
+        Ok(Some(change)) => report.changes.push(change),
+        Err(error) => report.errors.push(CompareError {
+            table: table.clone(),
+            name: name.to_owned(),
+            error,
+        }),
+    }
+}
+
+fn compare_sources(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    let (Some(source_prev), Some(source_curr)) = (
+        get_source_from_value(dep.value_prev),
+        get_source_from_value(dep.value_curr),
+    ) else {
+        return Ok(None);
+    };
+    if source_prev.kind() == source_curr.kind() {
+        return Ok(None);
+    }
+    Ok(Some(dep.change(ChangeKind::SourceChanged)))
+}
+
+fn compare_git_urls(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    let (Some(DepSource::Git { url: url_prev, .. }), Some(DepSource::Git { url: url_curr, .. })) = (
+        get_source_from_value(dep.value_prev),
+        get_source_from_value(dep.value_curr),
+    ) else {
+        return Ok(None);
+    };
+    if url_prev == url_curr {
+        return Ok(None);
+    }
+    Ok(Some(dep.change(ChangeKind::GitUrlChanged)))
+}
+
+fn compare_git_references(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    let (
+        Some(DepSource::Git {
+            reference: reference_prev,
+            ..
+        }),
+        Some(DepSource::Git {
+            reference: reference_curr,
+            ..
+        }),
+    ) = (
+        get_source_from_value(dep.value_prev),
+        get_source_from_value(dep.value_curr),
+    )
+    else {
+        return Ok(None);
+    };
+    if reference_prev == reference_curr {
+        return Ok(None);
+    }
+    Ok(Some(dep.change(ChangeKind::GitReferenceChanged)))
+}
+
+fn compare_registries(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    let (
+        Some(DepSource::Registry { name: name_prev }),
+        Some(DepSource::Registry { name: name_curr }),
+    ) = (
+        get_source_from_value(dep.value_prev),
+        get_source_from_value(dep.value_curr),
+    )
+    else {
+        return Ok(None);
+    };
+    if name_prev == name_curr {
+        return Ok(None);
+    }
+    Ok(Some(dep.change(ChangeKind::RegistryChanged)))
+}
+
+fn compare_deps(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    // smoelius: A registry is part of a dependency's identity, so versions from different
+    // registries are not comparable. A switch between registries is reported by
+    // `compare_registries`.
+    if let (
+        Some(DepSource::Registry { name: name_prev }),
+        Some(DepSource::Registry { name: name_curr }),
+    ) = (
+        get_source_from_value(dep.value_prev),
+        get_source_from_value(dep.value_curr),
+    ) && name_prev != name_curr
+    {
+        return Ok(None);
+    }
+    let Some(req_prev) = get_req_from_value(dep.value_prev)? else {
+        return Ok(None);
+    };
+    let Some(req_curr) = get_req_from_value(dep.value_curr)? else {
+        return Ok(None);
+    };
+    let minimum_version_curr = minimum_version_for_req(&req_curr)?;
+    let (kind, bump) = if req_prev.matches(&minimum_version_curr) {
+        // smoelius: The current requirement still admits the previous one's versions. But if its
+        // upper bound was raised (e.g., `>=1.0, <2` became `>=1.0, <3`), it now admits newer
+        // versions as well, which is reported as an upgrade. The upgrade is classified by
+        // comparing the greatest version the previous requirement admits to the least version
+        // newly admitted, i.e., the previous upper bound. So `=1.2.3` to `1.2.3` is a patch
+        // upgrade, and `~1.2` to `1.2` is a minor one.
+        let upper_bound_prev = upper_bound_for_req(&req_prev);
+        let upper_bound_curr = upper_bound_for_req(&req_curr);
+        if !upper_bound_raised(upper_bound_prev.as_ref(), upper_bound_curr.as_ref()) {
+            return Ok(None);
+        }
+        // smoelius: An upper bound cannot be raised if there was none.
+        let Some(upper_bound_prev) = upper_bound_prev else {
+            return Ok(None);
+        };
+        (
+            ChangeKind::Upgraded,
+            classify_bump(
+                &greatest_version_below(&upper_bound_prev),
+                &upper_bound_prev,
+            ),
+        )
+    } else {
+        let minimum_version_prev = minimum_version_for_req(&req_prev)?;
+        let kind = if minimum_version_curr < minimum_version_prev {
+            ChangeKind::Downgraded
+        } else {
+            ChangeKind::Upgraded
+        };
+        (
+            kind,
+            classify_bump(&minimum_version_prev, &minimum_version_curr),
+        )
+    };
+    Ok(Some(DependencyChange {
+        bump: Some(bump),
+        ..dep.change(kind)
+    }))
+}
+
+/// Classifies the change from `version_prev` to `version_curr` by the leftmost component that
+/// differs
+///
+/// Following Cargo, the change is breaking if the versions' leftmost nonzero components differ.
+/// So a minor change is breaking for `0.x` versions, e.g., `0.2.0` to `0.3.0`, and a patch change
+/// is breaking for `0.0.x` versions.
+fn classify_bump(version_prev: &Version, version_curr: &Version) -> Bump {
+    let level = if version_prev.major != version_curr.major {
+        BumpLevel::Major
+    } else if version_prev.minor != version_curr.minor {
+        BumpLevel::Minor
+    } else {
+        BumpLevel::Patch
+    };
+    let breaking = if version_prev.major != 0 || version_curr.major != 0 {
+        version_prev.major != version_curr.major
+    } else if version_prev.minor != 0 || version_curr.minor != 0 {
+        version_prev.minor != version_curr.minor
+    } else {
+        version_prev.patch != version_curr.patch
+    };
+    Bump { level, breaking }
+}
+
+/// Returns a change listing the features added (if `kind` is `FeaturesAdded`) or removed (if
+/// `kind` is `FeaturesRemoved`), or `None` if there are none
+fn compare_features(dep: &MatchedDep, kind: ChangeKind) -> Result<Option<DependencyChange>> {
+    let features_prev = get_features_from_value(dep.value_prev)?;
+    let features_curr = get_features_from_value(dep.value_curr)?;
+    let features = if kind == ChangeKind::FeaturesAdded {
+        features_curr.difference(&features_prev)
+    } else {
+        features_prev.difference(&features_curr)
+    }
+    .cloned()
+    .collect::<Vec<_>>();
+    if features.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(DependencyChange {
+        features,
+        ..dep.change(kind)
+    }))
+}
+
+fn compare_default_features(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    // smoelius: Cargo accepts `default_features` as a deprecated spelling of `default-features`.
+    let keys = ["default-features", "default_features"];
+    let default_features_prev = get_bool_from_value(dep.value_prev, &keys, true)?;
+    let default_features_curr = get_bool_from_value(dep.value_curr, &keys, true)?;
+    if default_features_prev == default_features_curr {
+        return Ok(None);
+    }
+    let kind = if default_features_curr {
+        ChangeKind::DefaultFeaturesEnabled
+    } else {
+        ChangeKind::DefaultFeaturesDisabled
+    };
+    Ok(Some(dep.change(kind)))
+}
+
+fn compare_optional(dep: &MatchedDep) -> Result<Option<DependencyChange>> {
+    let optional_prev = get_bool_from_value(dep.value_prev, &["optional"], false)?;
+    let optional_curr = get_bool_from_value(dep.value_curr, &["optional"], false)?;
+    if optional_prev == optional_curr {
+        return Ok(None);
+    }
+    let kind = if optional_curr {
+        ChangeKind::MadeOptional
+    } else {
+        ChangeKind::NoLongerOptional
+    };
+    Ok(Some(dep.change(kind)))
+}
+
+fn describe_added_dep(
+    table: &DepsTable,
+    name: &str,
+    value_curr: &toml::Value,
+) -> Result<Option<DependencyChange>> {
+    // smoelius: Git, path, and workspace dependencies have no version requirement to report, but
+    // their addition is still worth mentioning.
+    let req_curr = get_req_from_value(value_curr)?;
+    let minimum_version = req_curr.as_ref().map(minimum_version_for_req).transpose()?;
+    Ok(Some(DependencyChange {
+        table: table.clone(),
+        name: name.to_owned(),
+        prev_name: name.to_owned(),
+        kind: ChangeKind::Added,
+        req_prev: None,
+        req_curr,
+        req_prev_verbatim: None,
+        req_curr_verbatim: get_req_str_from_value(value_curr).map(ToOwned::to_owned),
+        source_prev: None,
+        source_curr: get_source_from_value(value_curr),
+        features: Vec::new(),
+        bump: None,
+        minimum_version,
+    }))
+}
+
+/// Returns the source of the dependency described by `value`, or `None` if the dependency is
+/// inherited from a workspace and could not be resolved
+fn get_source_from_value(value: &toml::Value) -> Option<DepSource> {
+    let Some(table) = value.as_table() else {
+        return Some(DepSource::Registry { name: None });
+    };
+    let get_str = |key: &str| {
+        table
+            .get(key)
+            .and_then(|value| value.as_str())
+            .map(ToOwned::to_owned)
+    };
+    if let Some(url) = get_str("git") {
+        let reference = get_str("rev")
+            .map(GitReference::Rev)
+            .or_else(|| get_str("tag").map(GitReference::Tag))
+            .or_else(|| get_str("branch").map(GitReference::Branch));
+        Some(DepSource::Git { url, reference })
+    } else if let Some(path) = get_str("path") {
+        Some(DepSource::Path { path })
+    } else if table
+        .get("workspace")
+        .and_then(toml::Value::as_bool)
+        .is_some_and(identity)
+    {
+        None
+    } else {
+        // smoelius: `crates-io` is Cargo's name for crates.io, not an alternate registry.
+        Some(DepSource::Registry {
+            name: get_str("registry").filter(|name| name != "crates-io"),
+        })
+    }
+}
+
+fn get_features_from_value(value: &toml::Value) -> Result<BTreeSet<String>> {
+    let Some(features) = value.as_table().and_then(|table| table.get("features")) else {
+        return Ok(BTreeSet::new());
+    };
+    let Some(features) = features.as_array() else {
+        bail!("`features` is not an array: {features}");
+    };
+    features
+        .iter()
+        .map(|feature| {
+            feature
+                .as_str()
+                .map(ToOwned::to_owned)
+                .ok_or_else(|| anyhow!("feature is not a string: {feature}"))
+        })
+        .collect()
+}
+
+/// Returns the value of the first of `keys` present in the dependency described by `value`, or
+/// `default` if none is present
+fn get_bool_from_value(value: &toml::Value, keys: &[&str], default: bool) -> Result<bool> {
+    let Some(table) = value.as_table() else {
+        return Ok(default);
+    };
+    let Some((key, value)) = keys
+        .iter()
+        .find_map(|&key| table.get(key).map(|value| (key, value)))
+    else {
+        return Ok(default);
+    };
+    value
+        .as_bool()
+        .ok_or_else(|| anyhow!("`{key}` is not a boolean: {value}"))
+}
+
+fn get_req_from_value(value: &toml::Value) -> Result<Option<VersionReq>> {
+    // smoelius: Skip git dependencies.
+    if value
+        .as_table()
+        .and_then(|table| table.get("git"))
+        .is_some()
+    {
+        return Ok(None);
+    }
+    // smoelius: Skip path dependencies, unless they also have a version requirement. Such a
+    // dependency is typically a publishable crate in the same workspace, and its version
+    // requirement is what users of the published crate get.
+    if value
+        .as_table()
+        .is_some_and(|table| table.contains_key("path") && !table.contains_key("version"))
+    {
+        return Ok(None);
+    }
+    // smoelius: Skip dependencies inherited from a workspace.
+    if value
+        .as_table()
+        .and_then(|table| table.get("workspace"))
+        .and_then(toml::Value::as_bool)
+        .is_some_and(identity)
+    {
+        return Ok(None);
+    }
+    let Some(req) = get_req_str_from_value(value) else {
+        bail!("failed to get version requirement");
+    };
+    let req = req.parse::<VersionReq>()?;
+    Ok(Some(req))
+}
+
+/// Returns the version requirement of the dependency described by `value`, as written in the
+/// manifest
+fn get_req_str_from_value(value: &toml::Value) -> Option<&str> {
+    value.as_str().or_else(|| {
+        value
+            .as_table()
+            .and_then(|table| table.get("version"))
+            .and_then(|value| value.as_str())
+    })
+}
+
+/// Returns the least version satisfying every comparator in `req`
+fn minimum_version_for_req(req: &VersionReq) -> Result<Version> {
+    let VersionReq { comparators } = req;
+    let minimum_version =
+        comparators
+            .iter()
+            .try_fold(Version::new(0, 0, 0), |minimum_version, comparator| {
+                let lower_bound = lower_bound_for_comparator(comparator)?;
+                Ok::<_, anyhow::Error>(minimum_version.max(lower_bound))
+            })?;
+    // smoelius: The greatest lower bound can fail to satisfy `req` if `req` also has an upper
+    // bound, e.g., `>=2.0, <1.0`.
+    ensure!(
+        req.matches(&minimum_version),
+        "no version satisfies requirement: {req}"
+    );
+    Ok(minimum_version)
+}
+
+/// Returns the least version satisfying `comparator`
+fn lower_bound_for_comparator(comparator: &Comparator) -> Result<Version> {
+    let Comparator {
+        op,
+        major,
+        minor,
+        patch,
+        pre,
+    } = comparator;
+    match op {
+        Op::Caret | Op::Exact | Op::GreaterEq | Op::Tilde | Op::Wildcard => {
+            let minor = minor.unwrap_or(0);
+            let patch = patch.unwrap_or(0);
+            Ok(Version {
+                major: *major,
+                minor,
+                patch,
+                pre: pre.clone(),
+                build: BuildMetadata::default(),
+            })
+        }
+        Op::Greater => {
+            // smoelius: If `comparator` has a pre-release, its release version is used, as it is
+            // greater than the pre-release.
+            if let (Some(minor), Some(patch)) = (minor, patch)
+                && !pre.is_empty()
+            {
+                Ok(Version::new(*major, *minor, *patch))
+            } else {
+                Ok(successor_of_prefix(*major, *minor, *patch))
+            }
+        }
+        Op::Less | Op::LessEq => Ok(Version::new(0, 0, 0)),
+        _ => bail!("unexpected operator: {op:?}"),
+    }
+}
+
+/// Returns the least version not satisfying `req` that is greater than all versions satisfying
+/// `req`, or `None` if `req` has no upper bound
+fn upper_bound_for_req(req: &VersionReq) -> Option<Version> {
+    let VersionReq { comparators } = req;
+    comparators
+        .iter()
+        .filter_map(upper_bound_for_comparator)
+        .min()
+}
+
+/// Returns the exclusive upper bound of `comparator`, or `None` if `comparator` has no upper bound
+///
+/// Pre-releases are ignored except in `<` comparators.
+fn upper_bound_for_comparator(comparator: &Comparator) -> Option<Version> {
+    let Comparator {
+        op,
+        major,
+        minor,
+        patch,
+        pre,
+    } = comparator;
+    match op {
+        Op::Caret => {
+            // smoelius: The leftmost nonzero component may not change, e.g., `^0.2.3` admits
+            // `0.2.x` but not `0.3.0`.
+            match (major, minor, patch) {
+                (0, Some(0), Some(patch)) => Some(Version::new(0, 0, patch + 1)),
+                (0, Some(minor), _) => Some(Version::new(0, minor + 1, 0)),
+                _ => Some(Version::new(major + 1, 0, 0)),
+            }
+        }
+        Op::Tilde | Op::Wildcard => Some(successor_of_prefix(*major, *minor, None)),
+        Op::Exact | Op::LessEq => Some(successor_of_prefix(*major, *minor, *patch)),
+        Op::Less => Some(Version {
+            major: *major,
+            minor: minor.unwrap_or(0),
+            patch: patch.unwrap_or(0),
+            pre: pre.clone(),
+            build: BuildMetadata::default(),
+        }),
+        _ => None,
+    }
+}
+
+/// Returns the least version greater than every version with the given prefix
+///
+/// A missing component means the prefix applies to all values of that component, e.g., the
+/// successor of `1.2` is `1.3.0`.
+fn successor_of_prefix(major: u64, minor: Option<u64>, patch: Option<u64>) -> Version {
+    match (minor, patch) {
+        (None, _) => Version::new(major + 1, 0, 0),
+        (Some(minor), None) => Version::new(major, minor + 1, 0),
+        (Some(minor), Some(patch)) => Version::new(major, minor, patch + 1),
+    }
+}
+
+/// Returns the greatest version less than `version`, ignoring pre-releases
+///
+/// A component of `u64::MAX` stands for "any", e.g., the greatest version below `1.3.0` is
+/// `1.2.<any>`. Only the returned version's components are meaningful, e.g., for `classify_bump`.
+fn greatest_version_below(version: &Version) -> Version {
+    match (version.major, version.minor, version.patch) {
+        (major, minor, patch @ 1..) => Version::new(major, minor, patch - 1),
+        (major, minor @ 1.., 0) => Version::new(major, minor - 1, u64::MAX),
+        (major @ 1.., 0, 0) => Version::new(major - 1, u64::MAX, u64::MAX),
+        (0, 0, 0) => Version::new(0, 0, 0),
+    }
+}
+
+/// Returns true if `upper_bound_curr` admits versions that `upper_bound_prev` does not
+///
+/// `None` means no upper bound.
+fn upper_bound_raised(
+    upper_bound_prev: Option<&Version>,
+    upper_bound_curr: Option<&Version>,
+) -> bool {
+    match (upper_bound_prev, upper_bound_curr) {
+        (Some(prev), Some(curr)) => curr > prev,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
@@ -43,9 +43,16 @@ fn run_case(case_dir: &Path) {
         .assert()
         .success();
 
-    copy_wc(case_dir.join("before.toml"), repo_dir.join("Cargo.toml")).unwrap();
+    // A case may provide a `before` directory in place of `before.toml`, e.g., to test
+    // repositories with multiple manifests.
+    let before_dir = case_dir.join("before");
+    if before_dir.exists() {
+        copy_dir(&before_dir, repo_dir);
+    } else {
+        copy_wc(case_dir.join("before.toml"), repo_dir.join("Cargo.toml")).unwrap();
+    }
 
-    git(&["add", "Cargo.toml"])
+    git(&["add", "--all"])
         .current_dir(repo_dir)
         .assert()
         .success();
@@ -69,7 +76,22 @@ fn run_case(case_dir: &Path) {
         git(&["tag", tag]).current_dir(repo_dir).assert().success();
     }
 
-    copy_wc(case_dir.join("after.toml"), repo_dir.join("Cargo.toml")).unwrap();
+    // Similarly, a case may provide an `after` directory in place of `after.toml`. Its contents
+    // replace the repository's, and are staged so that `git ls-files` reflects them.
+    let after_dir = case_dir.join("after");
+    if after_dir.exists() {
+        git(&["rm", "-r", "--quiet", "."])
+            .current_dir(repo_dir)
+            .assert()
+            .success();
+        copy_dir(&after_dir, repo_dir);
+        git(&["add", "--all"])
+            .current_dir(repo_dir)
+            .assert()
+            .success();
+    } else {
+        copy_wc(case_dir.join("after.toml"), repo_dir.join("Cargo.toml")).unwrap();
+    }
 
     let extra_dir = case_dir.join("extra");
     if extra_dir.exists() {
@@ -83,6 +105,15 @@ fn run_case(case_dir: &Path) {
             .success();
     }
 
+    // If a case has a `commit.txt` file, the "after" state is committed so that the case can refer
+    // to it by revision.
+    if case_dir.join("commit.txt").exists() {
+        git(&["commit", "--all", "-m", "after"])
+            .current_dir(repo_dir)
+            .assert()
+            .success();
+    }
+
     let expected_status: i32 = read_to_string_wc(case_dir.join("status.txt"))
         .unwrap()
         .trim()
@@ -91,14 +122,35 @@ fn run_case(case_dir: &Path) {
     let expected_stdout = read_to_string_wc(case_dir.join("stdout.txt")).unwrap();
     let expected_stderr = read_to_string_wc(case_dir.join("stderr.txt")).unwrap();
 
-    let args: Vec<String> = if case_dir.join("no_args.txt").exists() {
+    let mut args: Vec<String> = if case_dir.join("no_args.txt").exists() {
         vec![]
     } else {
         vec![prev_rev.to_string()]
     };
 
-    let mut cmd = cargo_bin_cmd!("whats-changed");
-    cmd.current_dir(repo_dir);
+    let args_path = case_dir.join("args.txt");
+    if args_path.exists() {
+        let extra_args = read_to_string_wc(&args_path).unwrap();
+        args.extend(extra_args.split_whitespace().map(ToString::to_string));
+    }
+
+    // If a case has a `subcommand.txt` file, whats-changed is run as a Cargo subcommand.
+    let mut cmd = if case_dir.join("subcommand.txt").exists() {
+        let mut cmd = cargo_bin_cmd!("cargo-whats-changed");
+        cmd.arg("whats-changed");
+        cmd
+    } else {
+        cargo_bin_cmd!("whats-changed")
+    };
+    // If a case has a `dir.txt` file, whats-changed is run in the subdirectory of the repository
+    // that the file names, e.g., a workspace member.
+    let dir_path = case_dir.join("dir.txt");
+    if dir_path.exists() {
+        let dir = read_to_string_wc(&dir_path).unwrap();
+        cmd.current_dir(repo_dir.join(dir.trim()));
+    } else {
+        cmd.current_dir(repo_dir);
+    }
     for arg in &args {
         cmd.arg(arg);
     }